@@ -0,0 +1,112 @@
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use ed25519_dalek::{SIGNATURE_LENGTH, Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Serialize, Deserialize)]
+struct Claims {
+    device_code: String,
+    issued_at: u64,
+    expires_at: u64,
+}
+
+/// Signs and verifies per-device bearer tokens.
+pub struct TokenAuth {
+    signing_key: SigningKey,
+}
+
+impl TokenAuth {
+    /// Builds a signer from a 32-byte seed, or a freshly generated one if none is given.
+    /// Tokens minted with a generated key stop verifying across restarts.
+    pub fn from_seed(seed: Option<[u8; 32]>) -> Self {
+        let signing_key = match seed {
+            Some(seed) => SigningKey::from_bytes(&seed),
+            None => SigningKey::generate(&mut rand::rngs::OsRng),
+        };
+        Self { signing_key }
+    }
+
+    /// Issues a token for `device_code`, valid for `ttl_secs` seconds from now.
+    pub fn issue(&self, device_code: &str, ttl_secs: u64) -> String {
+        let issued_at = now();
+        let claims = Claims {
+            device_code: device_code.to_string(),
+            issued_at,
+            expires_at: issued_at + ttl_secs,
+        };
+
+        let payload = serde_json::to_vec(&claims).expect("Claims always serializes");
+        let signature = self.signing_key.sign(&payload);
+
+        format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(payload),
+            URL_SAFE_NO_PAD.encode(signature.to_bytes())
+        )
+    }
+
+    /// Verifies `token`'s signature and expiry, returning the device code it was issued for.
+    pub fn verify(&self, token: &str) -> Option<String> {
+        let (payload_b64, signature_b64) = token.split_once('.')?;
+        let payload = URL_SAFE_NO_PAD.decode(payload_b64).ok()?;
+
+        let signature_bytes: [u8; SIGNATURE_LENGTH] =
+            URL_SAFE_NO_PAD.decode(signature_b64).ok()?.try_into().ok()?;
+        let signature = Signature::from_bytes(&signature_bytes);
+
+        let verifying_key: VerifyingKey = self.signing_key.verifying_key();
+        verifying_key.verify(&payload, &signature).ok()?;
+
+        let claims: Claims = serde_json::from_slice(&payload).ok()?;
+        if claims.expires_at < now() {
+            return None;
+        }
+
+        Some(claims.device_code)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system clock is after the Unix epoch")
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TokenAuth;
+    use std::{thread::sleep, time::Duration};
+
+    fn auth() -> TokenAuth {
+        TokenAuth::from_seed(Some([7u8; 32]))
+    }
+
+    #[test]
+    fn verifies_a_freshly_issued_token() {
+        let auth = auth();
+        let token = auth.issue("ABCD1234", 60);
+        assert_eq!(auth.verify(&token), Some("ABCD1234".to_string()));
+    }
+
+    #[test]
+    fn rejects_an_expired_token() {
+        let auth = auth();
+        let token = auth.issue("ABCD1234", 0);
+        sleep(Duration::from_secs(2));
+        assert_eq!(auth.verify(&token), None);
+    }
+
+    #[test]
+    fn rejects_a_token_signed_by_a_different_key() {
+        let issuer = auth();
+        let verifier = TokenAuth::from_seed(Some([9u8; 32]));
+        let token = issuer.issue("ABCD1234", 60);
+        assert_eq!(verifier.verify(&token), None);
+    }
+
+    #[test]
+    fn rejects_garbage_tokens() {
+        assert_eq!(auth().verify("not-a-token"), None);
+    }
+}
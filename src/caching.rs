@@ -0,0 +1,25 @@
+use actix_web::HttpRequest;
+use actix_web::http::header;
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use sha2::{Digest, Sha256};
+
+/// How long proxies and clients may cache a paste body for.
+const MAX_AGE_SECS: u64 = 365 * 24 * 60 * 60;
+
+/// Computes a strong `ETag` for `content`, as the quoted base64 of its SHA-256 digest.
+pub fn etag_for(content: &[u8]) -> String {
+    format!("\"{}\"", STANDARD.encode(Sha256::digest(content)))
+}
+
+/// The `Cache-Control` value to send alongside an `ETag`.
+pub fn cache_control() -> String {
+    format!("public, max-age={MAX_AGE_SECS}, immutable")
+}
+
+/// Whether `req`'s `If-None-Match` header matches `etag`.
+pub fn is_fresh(req: &HttpRequest, etag: &str) -> bool {
+    req.headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value == etag)
+}
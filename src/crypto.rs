@@ -0,0 +1,85 @@
+use aes_gcm::{
+    Aes256Gcm, Key, Nonce,
+    aead::{Aead, KeyInit, OsRng, rand_core::RngCore},
+};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+const NONCE_LEN: usize = 12;
+
+/// Derives a per-device 32-byte AES-256 key from the device code and the server master
+/// secret.
+fn derive_key(device_code: &str, master_secret: &[u8]) -> Key<Aes256Gcm> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(master_secret)
+        .expect("HMAC accepts keys of any length");
+    mac.update(device_code.as_bytes());
+    *Key::<Aes256Gcm>::from_slice(&mac.finalize().into_bytes())
+}
+
+/// Encrypts `plaintext` for `device_code`, returning `nonce || ciphertext` (the ciphertext
+/// includes the 16-byte GCM auth tag).
+pub fn encrypt(plaintext: &[u8], device_code: &str, master_secret: &[u8]) -> Vec<u8> {
+    let cipher = Aes256Gcm::new(&derive_key(device_code, master_secret));
+
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce_bytes);
+    let nonce = Nonce::from_slice(&nonce_bytes);
+
+    let ciphertext = cipher
+        .encrypt(nonce, plaintext)
+        .expect("AES-256-GCM encryption of an in-memory paste cannot fail");
+
+    let mut out = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(&nonce_bytes);
+    out.extend_from_slice(&ciphertext);
+    out
+}
+
+/// Decrypts a `nonce || ciphertext` blob produced by [`encrypt`].
+///
+/// Returns `None` if the blob is too short to contain a nonce or the auth tag fails to
+/// verify.
+pub fn decrypt(blob: &[u8], device_code: &str, master_secret: &[u8]) -> Option<Vec<u8>> {
+    if blob.len() < NONCE_LEN {
+        return None;
+    }
+    let (nonce_bytes, ciphertext) = blob.split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new(&derive_key(device_code, master_secret));
+    cipher.decrypt(Nonce::from_slice(nonce_bytes), ciphertext).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decrypt, encrypt};
+
+    #[test]
+    fn roundtrips_through_encrypt_and_decrypt() {
+        let blob = encrypt(b"hello, world", "ABCD1234", b"master secret");
+        assert_eq!(decrypt(&blob, "ABCD1234", b"master secret").unwrap(), b"hello, world");
+    }
+
+    #[test]
+    fn rejects_wrong_device_code() {
+        let blob = encrypt(b"hello, world", "ABCD1234", b"master secret");
+        assert!(decrypt(&blob, "WRONGCOD", b"master secret").is_none());
+    }
+
+    #[test]
+    fn rejects_wrong_master_secret() {
+        let blob = encrypt(b"hello, world", "ABCD1234", b"master secret");
+        assert!(decrypt(&blob, "ABCD1234", b"different secret").is_none());
+    }
+
+    #[test]
+    fn rejects_tampered_ciphertext() {
+        let mut blob = encrypt(b"hello, world", "ABCD1234", b"master secret");
+        let last = blob.len() - 1;
+        blob[last] ^= 0xff;
+        assert!(decrypt(&blob, "ABCD1234", b"master secret").is_none());
+    }
+
+    #[test]
+    fn rejects_blob_shorter_than_a_nonce() {
+        assert!(decrypt(&[0u8; 4], "ABCD1234", b"master secret").is_none());
+    }
+}
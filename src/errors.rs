@@ -1,4 +1,4 @@
-use actix_web::{HttpResponse, ResponseError, http::StatusCode};
+use actix_web::{HttpResponse, ResponseError, http::StatusCode, http::header};
 use serde::Serialize;
 use std::fmt::Formatter;
 
@@ -48,3 +48,37 @@ pub struct Unauthorized;
 
 impl_response_error_for_json!(Unauthorized, "Unauthorized", StatusCode::UNAUTHORIZED);
 
+#[derive(Debug)]
+pub struct BadRequest;
+
+impl_response_error_for_json!(BadRequest, "Bad Request", StatusCode::BAD_REQUEST);
+
+/// The caller's rate limit bucket is empty; `retry_after_secs` is how long until it has a
+/// token again.
+#[derive(Debug)]
+pub struct TooManyRequests {
+    pub retry_after_secs: u64,
+}
+
+impl ResponseError for TooManyRequests {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::TOO_MANY_REQUESTS
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let error_response = ErrorResponse {
+            error: "Too Many Requests".to_string(),
+            status: StatusCode::TOO_MANY_REQUESTS.as_u16(),
+        };
+        HttpResponse::build(StatusCode::TOO_MANY_REQUESTS)
+            .append_header((header::RETRY_AFTER, self.retry_after_secs.to_string()))
+            .json(error_response)
+    }
+}
+
+impl std::fmt::Display for TooManyRequests {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Too Many Requests")
+    }
+}
+
@@ -1,14 +1,27 @@
 #![deny(clippy::pedantic)]
 #![allow(clippy::unused_async)]
 
+mod auth;
+mod caching;
+mod crypto;
 mod errors;
 mod io;
+mod openapi;
 mod params;
+mod rate_limit;
+mod render;
+mod store;
 
 use crate::{
+    auth::TokenAuth,
+    caching::{cache_control, etag_for, is_fresh},
+    crypto::{decrypt, encrypt},
     errors::{BadRequest, NotFound, Unauthorized},
-    io::{PasteStore, generate_id, generate_unique_device_code, get_all_paste_ids, get_paste, store_paste},
-    params::{DeviceCode, HostHeader},
+    io::generate_id,
+    openapi::ApiDoc,
+    params::{DeviceCode, HostHeader, IsPlaintextRequest},
+    rate_limit::RateLimiter,
+    store::{MemoryStore, RedisStore, Store},
 };
 
 use actix_web::{
@@ -17,7 +30,13 @@ use actix_web::{
     web::{self, Bytes, Data, FormConfig, PayloadConfig},
 };
 use log::{error, info};
+use sha2::{Digest, Sha256};
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+
+/// How long a minted auth token stays valid for.
+const TOKEN_TTL_SECS: u64 = 60 * 60;
 
 #[derive(argh::FromArgs, Clone)]
 /// a pastebin.
@@ -31,21 +50,53 @@ pub struct BinArgs {
     /// maximum paste size in bytes (default. 32kB)
     #[argh(option, default = "32 * 1024")]
     max_paste_size: usize,
+    /// redis connection URL (e.g. redis://127.0.0.1:6379/), falling back to `$REDIS_URL`;
+    /// when neither is set, pastes are kept in an in-process `MemoryStore` instead
+    #[argh(option)]
+    redis_url: Option<String>,
+    /// rate limit token refill rate, in requests per second per device/IP (default: 1)
+    #[argh(option, default = "1.0")]
+    rate_limit_refill_per_sec: f64,
+    /// rate limit burst size, i.e. the maximum tokens a device/IP can bank (default: 20)
+    #[argh(option, default = "20")]
+    rate_limit_burst: u32,
 }
 
-fn check_auth(req: &HttpRequest, required_password: Option<&str>) -> Result<(), Unauthorized> {
-    if let Some(password) = required_password {
-        if let Some(header_value) = req.headers().get("App-Password") {
-            if let Ok(provided_password) = header_value.to_str() {
-                if provided_password == password {
-                    return Ok(());
-                }
+/// `device_code` is the `Device-Code` header the request is claiming to act as, already
+/// validated by the `DeviceCode` extractor. A bearer token only authenticates if it was
+/// issued for that exact device code.
+fn check_auth(
+    req: &HttpRequest,
+    required_password: Option<&str>,
+    device_code: Option<&str>,
+    token_auth: &TokenAuth,
+) -> Result<(), Unauthorized> {
+    let Some(password) = required_password else {
+        return Ok(());
+    };
+
+    if let Some(token) = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    {
+        if let Some(device_code) = device_code {
+            if token_auth.verify(token).as_deref() == Some(device_code) {
+                return Ok(());
+            }
+        }
+    }
+
+    if let Some(header_value) = req.headers().get("App-Password") {
+        if let Ok(provided_password) = header_value.to_str() {
+            if provided_password == password {
+                return Ok(());
             }
         }
-        Err(Unauthorized)
-    } else {
-        Ok(())
     }
+
+    Err(Unauthorized)
 }
 
 #[actix_web::main]
@@ -59,9 +110,33 @@ async fn main() -> std::io::Result<()> {
 
     let args: BinArgs = argh::from_env();
     let password = std::env::var("APP_PASSWORD").ok();
+    let encryption_key = std::env::var("PASTE_ENCRYPTION_KEY")
+        .expect("PASTE_ENCRYPTION_KEY must be set")
+        .into_bytes();
+    let redis_url = args.redis_url.clone().or_else(|| std::env::var("REDIS_URL").ok());
+    let token_seed = std::env::var("TOKEN_SIGNING_KEY").ok().map(|secret| {
+        let mut seed = [0u8; 32];
+        seed.copy_from_slice(&Sha256::digest(secret.as_bytes()));
+        seed
+    });
 
-    let store = Data::new(PasteStore::default());
+    let store: Box<dyn Store> = match redis_url {
+        Some(url) => {
+            info!("Using Redis-backed paste storage");
+            Box::new(
+                RedisStore::connect(&url)
+                    .await
+                    .expect("failed to connect to Redis"),
+            )
+        }
+        None => Box::new(MemoryStore::default()),
+    };
+
+    let store = Data::new(store);
     let auth_config = Data::new(password);
+    let encryption_key = Data::new(encryption_key);
+    let token_auth = Data::new(TokenAuth::from_seed(token_seed));
+    let rate_limiter = RateLimiter::new(args.rate_limit_refill_per_sec, args.rate_limit_burst);
 
     let server = HttpServer::new({
         let args = args.clone();
@@ -70,10 +145,15 @@ async fn main() -> std::io::Result<()> {
             App::new()
                 .app_data(store.clone())
                 .app_data(auth_config.clone())
+                .app_data(encryption_key.clone())
+                .app_data(token_auth.clone())
                 .app_data(PayloadConfig::default().limit(args.max_paste_size))
                 .app_data(FormConfig::default().limit(args.max_paste_size))
+                .wrap(rate_limiter.clone())
                 .wrap(actix_web::middleware::Compress::default())
+                .service(SwaggerUi::new("/docs/{_:.*}").url("/openapi.json", ApiDoc::openapi()))
                 .route("/", web::get().to(index))
+                .route("/auth", web::post().to(issue_auth_token))
                 .route("/device", web::post().to(generate_device_code))
                 .route("/all", web::get().to(list_all_pastes))
                 .route("/", web::post().to(submit))
@@ -93,116 +173,147 @@ async fn main() -> std::io::Result<()> {
     server.bind(args.bind_addr)?.run().await
 }
 
-#[derive(serde::Serialize)]
-struct IndexResponse {
-    message: String,
-    endpoints: Vec<ApiEndpoint>,
+/// Redirects to the interactive API docs, since the route table now lives in the
+/// generated OpenAPI document rather than a hand-written response.
+async fn index() -> HttpResponse {
+    HttpResponse::Found()
+        .append_header((header::LOCATION, "/docs/"))
+        .finish()
 }
 
-#[derive(serde::Serialize)]
-struct ApiEndpoint {
-    method: String,
-    path: String,
-    description: String,
-}
+#[utoipa::path(
+    post,
+    path = "/auth",
+    tag = "bin",
+    security(("app_password" = []), ("device_code" = [])),
+    responses(
+        (status = 200, description = "A signed, expiring bearer token for the given device", body = String),
+        (status = 400, description = "Missing or invalid Device-Code header"),
+        (status = 401, description = "Missing or incorrect App-Password"),
+    )
+)]
+async fn issue_auth_token(
+    req: HttpRequest,
+    device_code: DeviceCode,
+    auth_config: Data<Option<String>>,
+    token_auth: Data<TokenAuth>,
+) -> Result<String, Error> {
+    check_auth(&req, auth_config.as_deref(), device_code.0.as_deref(), &token_auth)?;
 
-async fn index() -> Result<HttpResponse, Error> {
-    let response = IndexResponse {
-        message: "Bin API - A pastebin service".to_string(),
-        endpoints: vec![
-            ApiEndpoint {
-                method: "GET".to_string(),
-                path: "/".to_string(),
-                description: "Get API information".to_string(),
-            },
-            ApiEndpoint {
-                method: "POST".to_string(),
-                path: "/".to_string(),
-                description: "Create a new paste (form data)".to_string(),
-            },
-            ApiEndpoint {
-                method: "PUT".to_string(),
-                path: "/".to_string(),
-                description: "Create a new paste (raw data)".to_string(),
-            },
-            ApiEndpoint {
-                method: "POST".to_string(),
-                path: "/device".to_string(),
-                description: "Generate a unique device code".to_string(),
-            },
-            ApiEndpoint {
-                method: "GET".to_string(),
-                path: "/all".to_string(),
-                description: "Get all paste IDs for your device".to_string(),
-            },
-            ApiEndpoint {
-                method: "GET".to_string(),
-                path: "/{paste}".to_string(),
-                description: "Get paste content by ID".to_string(),
-            },
-        ],
-    };
-    Ok(HttpResponse::Ok().json(response))
+    let device_code = device_code.0.ok_or(BadRequest)?;
+    Ok(token_auth.issue(&device_code, TOKEN_TTL_SECS))
 }
 
+#[utoipa::path(
+    post,
+    path = "/device",
+    tag = "bin",
+    security(("app_password" = [])),
+    responses(
+        (status = 200, description = "A freshly generated device code", body = String),
+        (status = 401, description = "Missing or incorrect App-Password"),
+    )
+)]
 async fn generate_device_code(
     req: HttpRequest,
-    store: Data<PasteStore>,
+    store: Data<Box<dyn Store>>,
     auth_config: Data<Option<String>>,
+    token_auth: Data<TokenAuth>,
 ) -> Result<String, Error> {
-    check_auth(&req, auth_config.as_deref())?;
-    
-    let device_code = generate_unique_device_code(&store);
-    
+    check_auth(&req, auth_config.as_deref(), None, &token_auth)?;
+
+    let device_code = store.generate_unique_device_code().await;
+
     Ok(device_code)
 }
 
+#[utoipa::path(
+    get,
+    path = "/all",
+    tag = "bin",
+    security(("app_password" = []), ("device_code" = [])),
+    responses(
+        (status = 200, description = "Paste IDs owned by the requesting device", body = [String]),
+        (status = 400, description = "Missing or invalid Device-Code header"),
+        (status = 401, description = "Missing or incorrect App-Password"),
+    )
+)]
 async fn list_all_pastes(
     req: HttpRequest,
     device_code: DeviceCode,
-    store: Data<PasteStore>,
+    store: Data<Box<dyn Store>>,
     auth_config: Data<Option<String>>,
+    token_auth: Data<TokenAuth>,
 ) -> Result<HttpResponse, Error> {
-    check_auth(&req, auth_config.as_deref())?;
-    
+    check_auth(&req, auth_config.as_deref(), device_code.0.as_deref(), &token_auth)?;
+
     let device_code = device_code.0.ok_or(BadRequest)?;
-    let paste_ids = get_all_paste_ids(&store, &device_code);
+    let paste_ids = store.get_all_paste_ids(&device_code).await;
     Ok(HttpResponse::Ok().json(paste_ids))
 }
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, utoipa::ToSchema)]
 struct IndexForm {
     val: Bytes,
 }
 
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "bin",
+    security(("app_password" = []), ("device_code" = [])),
+    request_body(content = IndexForm, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 302, description = "Paste created; Location header points at it"),
+        (status = 400, description = "Missing or invalid Device-Code header"),
+        (status = 401, description = "Missing or incorrect App-Password"),
+    )
+)]
 async fn submit(
     req: HttpRequest,
     input: web::Form<IndexForm>,
     device_code: DeviceCode,
-    store: Data<PasteStore>,
+    store: Data<Box<dyn Store>>,
     auth_config: Data<Option<String>>,
+    token_auth: Data<TokenAuth>,
+    encryption_key: Data<Vec<u8>>,
 ) -> Result<HttpResponse, Error> {
-    check_auth(&req, auth_config.as_deref())?;
-    
+    check_auth(&req, auth_config.as_deref(), device_code.0.as_deref(), &token_auth)?;
+
     let device_code = device_code.0.ok_or(BadRequest)?;
     let id = generate_id();
     let uri = format!("/{id}");
-    store_paste(&store, id, input.into_inner().val, device_code);
+    let content = Bytes::from(encrypt(&input.into_inner().val, &device_code, &encryption_key));
+    store.store_paste(id, content, device_code).await;
     Ok(HttpResponse::Found()
         .append_header((header::LOCATION, uri))
         .finish())
 }
 
+#[utoipa::path(
+    put,
+    path = "/",
+    tag = "bin",
+    security(("app_password" = []), ("device_code" = [])),
+    request_body(content = String, content_type = "application/octet-stream"),
+    responses(
+        (status = 200, description = "URL of the created paste", body = String),
+        (status = 400, description = "Missing or invalid Device-Code header"),
+        (status = 401, description = "Missing or incorrect App-Password"),
+    )
+)]
 async fn submit_raw(
     req: HttpRequest,
     data: Bytes,
     host: HostHeader,
     device_code: DeviceCode,
-    store: Data<PasteStore>,
+    store: Data<Box<dyn Store>>,
     auth_config: Data<Option<String>>,
+    token_auth: Data<TokenAuth>,
+    encryption_key: Data<Vec<u8>>,
 ) -> Result<String, Error> {
-    check_auth(&req, auth_config.as_deref())?;
-    
+    check_auth(&req, auth_config.as_deref(), device_code.0.as_deref(), &token_auth)?;
+
     let device_code = device_code.0.ok_or(BadRequest)?;
     let id = generate_id();
     let uri = if let Some(Ok(host)) = host.0.as_ref().map(|v| std::str::from_utf8(v.as_bytes())) {
@@ -211,31 +322,72 @@ async fn submit_raw(
         format!("/{id}\n")
     };
 
-    store_paste(&store, id, data, device_code);
+    let content = Bytes::from(encrypt(&data, &device_code, &encryption_key));
+    store.store_paste(id, content, device_code).await;
 
     Ok(uri)
 }
 
 
+#[utoipa::path(
+    get,
+    path = "/{paste}",
+    tag = "bin",
+    security(("app_password" = []), ("device_code" = [])),
+    params(
+        ("paste" = String, Path, description = "Paste id, optionally suffixed with a `.ext` syntax hint"),
+    ),
+    responses(
+        (status = 200, description = "Paste content", body = String),
+        (status = 304, description = "Client's cached copy (per If-None-Match) is still fresh"),
+        (status = 400, description = "Missing or invalid Device-Code header"),
+        (status = 401, description = "Missing or incorrect App-Password, or the device doesn't own this paste"),
+    )
+)]
 async fn show_paste(
     req: HttpRequest,
     key: actix_web::web::Path<String>,
     device_code: DeviceCode,
-    store: Data<PasteStore>,
+    is_plaintext: IsPlaintextRequest,
+    store: Data<Box<dyn Store>>,
     auth_config: Data<Option<String>>,
+    token_auth: Data<TokenAuth>,
+    encryption_key: Data<Vec<u8>>,
 ) -> Result<HttpResponse, Error> {
-    check_auth(&req, auth_config.as_deref())?;
-    
+    check_auth(&req, auth_config.as_deref(), device_code.0.as_deref(), &token_auth)?;
+
     let device_code = device_code.0.ok_or(BadRequest)?;
     let mut splitter = key.splitn(2, '.');
     let key = splitter.next().unwrap();
-    let _ext = splitter.next();
+    let ext = splitter.next();
+
+    let stored = store.get_paste(key, &device_code).await.ok_or(Unauthorized)?;
+    let entry = Bytes::from(decrypt(&stored, &device_code, &encryption_key).ok_or(Unauthorized)?);
+
+    let etag = etag_for(&entry);
+    if is_fresh(&req, &etag) {
+        return Ok(HttpResponse::NotModified()
+            .append_header((header::ETAG, etag))
+            .append_header((header::CACHE_CONTROL, cache_control()))
+            .finish());
+    }
+
+    if *is_plaintext {
+        return Ok(HttpResponse::Ok()
+            .append_header((header::ETAG, etag))
+            .append_header((header::CACHE_CONTROL, cache_control()))
+            .content_type("text/plain; charset=utf-8")
+            .body(entry));
+    }
 
-    let entry = get_paste(&store, key, &device_code).ok_or(Unauthorized)?;
+    let content = String::from_utf8_lossy(&entry);
+    let html = render::highlight(&content, ext);
 
     Ok(HttpResponse::Ok()
-        .content_type("text/plain; charset=utf-8")
-        .body(entry))
+        .append_header((header::ETAG, etag))
+        .append_header((header::CACHE_CONTROL, cache_control()))
+        .content_type("text/html; charset=utf-8")
+        .body(html))
 }
 
 
@@ -0,0 +1,38 @@
+use utoipa::{
+    Modify, OpenApi,
+    openapi::security::{ApiKey, ApiKeyValue, SecurityScheme},
+};
+
+/// Registers the `Device-Code` and `App-Password` headers as security schemes.
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "device_code",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("Device-Code"))),
+        );
+        components.add_security_scheme(
+            "app_password",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("App-Password"))),
+        );
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        crate::issue_auth_token,
+        crate::generate_device_code,
+        crate::list_all_pastes,
+        crate::submit,
+        crate::submit_raw,
+        crate::show_paste,
+    ),
+    modifiers(&SecurityAddon),
+    tags(
+        (name = "bin", description = "A pastebin service")
+    )
+)]
+pub struct ApiDoc;
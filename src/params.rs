@@ -56,10 +56,14 @@ impl FromRequest for HostHeader {
     }
 }
 
+/// Whether `code` is a valid device code: an 8 character alphanumeric (A-Z, 0-9) string.
+pub fn is_valid_device_code(code: &str) -> bool {
+    code.len() == 8 && code.chars().all(|c| c.is_ascii_alphanumeric() && (c.is_ascii_uppercase() || c.is_ascii_digit()))
+}
+
 /// Gets the Device-Code header from the request.
 ///
 /// The inner value will be `None` if there was no Device-Code header or if it's invalid.
-/// Valid device codes are 8 character alphanumeric (A-Z, 0-9) strings.
 pub struct DeviceCode(pub Option<String>);
 
 impl FromRequest for DeviceCode {
@@ -71,11 +75,9 @@ impl FromRequest for DeviceCode {
             .headers()
             .get("Device-Code")
             .and_then(|h| h.to_str().ok())
-            .filter(|code| {
-                code.len() == 8 && code.chars().all(|c| c.is_ascii_alphanumeric() && (c.is_ascii_uppercase() || c.is_ascii_digit()))
-            })
+            .filter(|code| is_valid_device_code(code))
             .map(String::from);
-        
+
         ok(Self(device_code))
     }
 }
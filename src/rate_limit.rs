@@ -0,0 +1,180 @@
+use std::{
+    collections::HashMap,
+    future::{Ready, ready},
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use actix_web::{
+    Error,
+    dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready},
+};
+use futures::future::LocalBoxFuture;
+use parking_lot::RwLock;
+
+use crate::{errors::TooManyRequests, params::is_valid_device_code};
+
+/// How often to sweep idle buckets out of the map.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(300);
+/// How long a bucket can go untouched before it's swept.
+const IDLE_TTL: Duration = Duration::from_secs(600);
+
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+struct State {
+    buckets: HashMap<String, Bucket>,
+    last_sweep: Instant,
+}
+
+/// Per-key token-bucket rate limiter, keyed on the `Device-Code` header (falling back to
+/// the peer IP for unauthenticated requests). Registered with `App::wrap`, so one instance
+/// is shared by every request on a worker.
+#[derive(Clone)]
+pub struct RateLimiter {
+    state: Arc<RwLock<State>>,
+    refill_per_sec: f64,
+    burst: f64,
+}
+
+impl RateLimiter {
+    /// `refill_per_sec` tokens are added to a key's bucket per second, up to `burst`
+    /// tokens; each request consumes one token.
+    pub fn new(refill_per_sec: f64, burst: u32) -> Self {
+        Self {
+            state: Arc::new(RwLock::new(State {
+                buckets: HashMap::new(),
+                last_sweep: Instant::now(),
+            })),
+            refill_per_sec,
+            burst: f64::from(burst),
+        }
+    }
+
+    fn key_for(req: &ServiceRequest) -> String {
+        req.headers()
+            .get("Device-Code")
+            .and_then(|value| value.to_str().ok())
+            .filter(|code| is_valid_device_code(code))
+            .map(String::from)
+            .unwrap_or_else(|| {
+                req.peer_addr()
+                    .map_or_else(|| "unknown".to_string(), |addr| addr.ip().to_string())
+            })
+    }
+
+    /// Takes a token for `key`, returning `Err(retry_after_secs)` when the bucket is empty.
+    fn try_acquire(&self, key: &str) -> Result<(), u64> {
+        let mut state = self.state.write();
+        let now = Instant::now();
+
+        if now.duration_since(state.last_sweep) >= SWEEP_INTERVAL {
+            state
+                .buckets
+                .retain(|_, bucket| now.duration_since(bucket.last_refill) < IDLE_TTL);
+            state.last_sweep = now;
+        }
+
+        let bucket = state.buckets.entry(key.to_string()).or_insert_with(|| Bucket {
+            tokens: self.burst,
+            last_refill: now,
+        });
+
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_per_sec).min(self.burst);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            let retry_after = ((1.0 - bucket.tokens) / self.refill_per_sec).ceil() as u64;
+            Err(retry_after.max(1))
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimiter
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RateLimiterMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimiterMiddleware {
+            service: Rc::new(service),
+            limiter: self.clone(),
+        }))
+    }
+}
+
+pub struct RateLimiterMiddleware<S> {
+    service: Rc<S>,
+    limiter: RateLimiter,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimiterMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let key = Self::key_for(&req);
+        let outcome = self.limiter.try_acquire(&key);
+        let service = Rc::clone(&self.service);
+
+        Box::pin(async move {
+            match outcome {
+                Ok(()) => service.call(req).await,
+                Err(retry_after_secs) => Err(TooManyRequests { retry_after_secs }.into()),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RateLimiter;
+
+    #[test]
+    fn allows_up_to_the_burst_then_rejects() {
+        let limiter = RateLimiter::new(1.0, 2);
+        assert!(limiter.try_acquire("device").is_ok());
+        assert!(limiter.try_acquire("device").is_ok());
+        assert!(limiter.try_acquire("device").is_err());
+    }
+
+    #[test]
+    fn keys_are_independent() {
+        let limiter = RateLimiter::new(1.0, 1);
+        assert!(limiter.try_acquire("a").is_ok());
+        assert!(limiter.try_acquire("b").is_ok());
+        assert!(limiter.try_acquire("a").is_err());
+    }
+
+    #[test]
+    fn retry_after_is_at_least_one_second() {
+        let limiter = RateLimiter::new(1.0, 1);
+        assert!(limiter.try_acquire("device").is_ok());
+        match limiter.try_acquire("device") {
+            Err(retry_after_secs) => assert!(retry_after_secs >= 1),
+            Ok(()) => panic!("expected the bucket to be empty"),
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use std::sync::LazyLock;
+
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{Style, ThemeSet},
+    html::{IncludeBackground, styled_line_to_highlighted_html},
+    parsing::SyntaxSet,
+    util::LinesWithEndings,
+};
+
+static SYNTAX_SET: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+static THEME_SET: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
+
+/// Renders paste content as an inline-styled HTML `<pre>` block, highlighted according to
+/// `ext` (the extension parsed off the end of the paste id, e.g. `rs` for `/{paste}.rs`).
+/// Falls back to plain text when `ext` is absent or doesn't match a known syntax.
+pub fn highlight(content: &str, ext: Option<&str>) -> String {
+    let theme = &THEME_SET.themes["InspiredGitHub"];
+
+    let syntax = ext
+        .and_then(|ext| SYNTAX_SET.find_syntax_by_extension(ext))
+        .unwrap_or_else(|| SYNTAX_SET.find_syntax_plain_text());
+
+    let mut highlighter = HighlightLines::new(syntax, theme);
+    let mut body = String::new();
+
+    for line in LinesWithEndings::from(content) {
+        let ranges: Vec<(Style, &str)> = highlighter
+            .highlight_line(line, &SYNTAX_SET)
+            .unwrap_or_default();
+        body.push_str(&styled_line_to_highlighted_html(
+            &ranges,
+            IncludeBackground::Yes,
+        ).unwrap_or_default());
+    }
+
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"></head><body><pre>{body}</pre></body></html>"
+    )
+}
@@ -0,0 +1,116 @@
+use super::{DEVICE_PASTE_LIMIT, Paste, Store};
+use crate::io::random_device_code;
+
+use actix_web::web::Bytes;
+use async_trait::async_trait;
+use linked_hash_map::LinkedHashMap;
+use parking_lot::RwLock;
+use std::collections::HashSet;
+
+/// The original `Store` impl: an insertion-ordered map guarded by a lock. Pastes are lost
+/// on restart and not shared across instances.
+#[derive(Default)]
+pub struct MemoryStore {
+    entries: RwLock<LinkedHashMap<String, Paste>>,
+}
+
+#[async_trait]
+impl Store for MemoryStore {
+    async fn store_paste(&self, id: String, content: Bytes, device_code: String) {
+        self.purge_device_old(&device_code).await;
+
+        self.entries
+            .write()
+            .insert(id, Paste { content, device_code });
+    }
+
+    async fn get_paste(&self, id: &str, device_code: &str) -> Option<Bytes> {
+        self.entries.read().get(id).and_then(|paste| {
+            if paste.device_code == device_code {
+                Some(paste.content.clone())
+            } else {
+                None
+            }
+        })
+    }
+
+    async fn get_all_paste_ids(&self, device_code: &str) -> Vec<String> {
+        let mut ids: Vec<String> = self
+            .entries
+            .read()
+            .iter()
+            .filter(|(_, paste)| paste.device_code == device_code)
+            .map(|(id, _)| id.clone())
+            .collect();
+        ids.reverse();
+        ids
+    }
+
+    async fn purge_device_old(&self, device_code: &str) {
+        let mut entries = self.entries.write();
+
+        let device_pastes: Vec<_> = entries
+            .iter()
+            .filter(|(_, paste)| paste.device_code == device_code)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        if device_pastes.len() >= DEVICE_PASTE_LIMIT {
+            let to_remove = device_pastes.len() - DEVICE_PASTE_LIMIT + 1;
+            for id in &device_pastes[..to_remove] {
+                entries.remove(id);
+            }
+        }
+    }
+
+    async fn generate_unique_device_code(&self) -> String {
+        let existing_devices: HashSet<String> = self
+            .entries
+            .read()
+            .values()
+            .map(|paste| paste.device_code.clone())
+            .collect();
+
+        loop {
+            let device_code = random_device_code();
+            if !existing_devices.contains(&device_code) {
+                return device_code;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn purges_the_oldest_paste_past_the_device_limit() {
+        let store = MemoryStore::default();
+
+        for i in 0..=DEVICE_PASTE_LIMIT {
+            store
+                .store_paste(format!("id{i}"), Bytes::from_static(b"x"), "DEVICE01".to_string())
+                .await;
+        }
+
+        let expected: Vec<String> = (1..=DEVICE_PASTE_LIMIT).rev().map(|i| format!("id{i}")).collect();
+        assert_eq!(store.get_all_paste_ids("DEVICE01").await, expected);
+    }
+
+    #[actix_web::test]
+    async fn lists_paste_ids_newest_first() {
+        let store = MemoryStore::default();
+        store
+            .store_paste("id0".to_string(), Bytes::from_static(b"x"), "DEVICE01".to_string())
+            .await;
+        store
+            .store_paste("id1".to_string(), Bytes::from_static(b"x"), "DEVICE01".to_string())
+            .await;
+
+        assert_eq!(
+            store.get_all_paste_ids("DEVICE01").await,
+            vec!["id1".to_string(), "id0".to_string()]
+        );
+    }
+}
@@ -0,0 +1,42 @@
+mod memory;
+mod redis;
+
+pub use memory::MemoryStore;
+pub use redis::RedisStore;
+
+use actix_web::web::Bytes;
+use async_trait::async_trait;
+
+/// A single stored paste: its (possibly encrypted) content and the device code that owns it.
+#[derive(Clone)]
+pub struct Paste {
+    pub content: Bytes,
+    pub device_code: String,
+}
+
+/// Maximum number of pastes a single device may keep; storing one more purges the oldest.
+pub const DEVICE_PASTE_LIMIT: usize = 2;
+
+/// Backing storage for pastes and device codes. Handlers hold this as a trait object so
+/// the backend can be swapped between `MemoryStore` and `RedisStore`.
+#[async_trait]
+pub trait Store: Send + Sync {
+    /// Stores a paste under `id` for `device_code`, purging that device's oldest pastes
+    /// first if it's at [`DEVICE_PASTE_LIMIT`].
+    async fn store_paste(&self, id: String, content: Bytes, device_code: String);
+
+    /// Gets a paste by id if `device_code` owns it.
+    ///
+    /// Returns `None` if the paste doesn't exist or the device doesn't own it.
+    async fn get_paste(&self, id: &str, device_code: &str) -> Option<Bytes>;
+
+    /// Gets all paste ids owned by `device_code`, most recently created first.
+    async fn get_all_paste_ids(&self, device_code: &str) -> Vec<String>;
+
+    /// Makes room for one more paste: if `device_code` has [`DEVICE_PASTE_LIMIT`] or more
+    /// pastes, removes its oldest so that `DEVICE_PASTE_LIMIT - 1` remain.
+    async fn purge_device_old(&self, device_code: &str);
+
+    /// Generates an 8-character device code that doesn't already exist.
+    async fn generate_unique_device_code(&self) -> String;
+}
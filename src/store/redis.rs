@@ -0,0 +1,93 @@
+use super::{DEVICE_PASTE_LIMIT, Store};
+use crate::io::random_device_code;
+
+use actix_web::web::Bytes;
+use async_trait::async_trait;
+use fred::prelude::*;
+
+/// `Store` impl backed by Redis via a pooled async client.
+///
+/// Each paste is a `paste:{id}` key holding `device_code\0content`. A device's paste ids
+/// live in a `device:{device_code}` list, newest pushed to the head.
+pub struct RedisStore {
+    pool: RedisPool,
+}
+
+impl RedisStore {
+    /// Connects a small pool of clients to `url` (e.g. `redis://127.0.0.1:6379`).
+    pub async fn connect(url: &str) -> Result<Self, RedisError> {
+        let config = RedisConfig::from_url(url)?;
+        let pool = Builder::from_config(config).build_pool(5)?;
+        pool.init().await?;
+        Ok(Self { pool })
+    }
+
+    fn paste_key(id: &str) -> String {
+        format!("paste:{id}")
+    }
+
+    fn device_key(device_code: &str) -> String {
+        format!("device:{device_code}")
+    }
+}
+
+#[async_trait]
+impl Store for RedisStore {
+    async fn store_paste(&self, id: String, content: Bytes, device_code: String) {
+        self.purge_device_old(&device_code).await;
+
+        let mut value = Vec::with_capacity(device_code.len() + 1 + content.len());
+        value.extend_from_slice(device_code.as_bytes());
+        value.push(0);
+        value.extend_from_slice(&content);
+
+        let _: Result<(), RedisError> = self.pool.set(Self::paste_key(&id), value, None, None, false).await;
+        let _: Result<(), RedisError> = self.pool.lpush(Self::device_key(&device_code), id).await;
+    }
+
+    async fn get_paste(&self, id: &str, device_code: &str) -> Option<Bytes> {
+        let value: Vec<u8> = self.pool.get(Self::paste_key(id)).await.ok()?;
+        let separator = value.iter().position(|&b| b == 0)?;
+        let (owner, rest) = value.split_at(separator);
+        if owner != device_code.as_bytes() {
+            return None;
+        }
+        Some(Bytes::copy_from_slice(&rest[1..]))
+    }
+
+    async fn get_all_paste_ids(&self, device_code: &str) -> Vec<String> {
+        self.pool
+            .lrange(Self::device_key(device_code), 0, -1)
+            .await
+            .unwrap_or_default()
+    }
+
+    async fn purge_device_old(&self, device_code: &str) {
+        let key = Self::device_key(device_code);
+        let len: i64 = self.pool.llen(&key).await.unwrap_or(0);
+
+        if len >= DEVICE_PASTE_LIMIT as i64 {
+            let keep = DEVICE_PASTE_LIMIT as i64 - 1;
+            if let Ok(stale_ids) = self.pool.lrange::<Vec<String>, _>(&key, keep, -1).await {
+                for id in stale_ids {
+                    let _: Result<(), RedisError> = self.pool.del(Self::paste_key(&id)).await;
+                }
+            }
+            let _: Result<(), RedisError> = self.pool.ltrim(&key, 0, keep - 1).await;
+        }
+    }
+
+    async fn generate_unique_device_code(&self) -> String {
+        loop {
+            let device_code = random_device_code();
+            let exists: bool = self
+                .pool
+                .exists(Self::device_key(&device_code))
+                .await
+                .unwrap_or(false);
+            if !exists {
+                return device_code;
+            }
+        }
+    }
+}